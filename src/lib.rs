@@ -2,11 +2,15 @@ use bitflags::bitflags;
 use bitflags_serde_shim::impl_serde_for_bitflags;
 use serde::{Deserialize, Serialize};
 use std::{
-    io::{Error, Result},
+    fmt,
+    io::{Error, Result, Write},
     path::Path,
-    process::Command,
+    process::{Command, Stdio},
 };
 
+#[cfg(feature = "xattr")]
+mod xattr;
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum AceType {
     Allow,
@@ -68,45 +72,128 @@ pub struct Acl {
     pub aces: Vec<Ace>,
 }
 
-impl Acl {
-    /// Parses the output of the nfs4_getfacl command to a Acl
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Acl> {
-        if !path.as_ref().exists() {
-            return Err(Error::new(
-                std::io::ErrorKind::NotFound,
-                "The file does not exist",
-            ));
+bitflags! {
+    #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+    pub struct PosixPermissions: u8 {
+        const READ    = 0b100;
+        const WRITE   = 0b010;
+        const EXECUTE = 0b001;
+    }
+}
+
+impl_serde_for_bitflags!(PosixPermissions);
+
+/// The entry kind of a classic POSIX ACL entry.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Copy, Clone, Debug)]
+pub enum PosixAclTag {
+    UserObj,
+    GroupObj,
+    Other,
+    NamedUser,
+    NamedGroup,
+    Mask,
+}
+
+/// A single entry of a classic POSIX rwx ACL.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct PosixAce {
+    pub tag: PosixAclTag,
+    pub id: Option<String>,
+    pub permissions: PosixPermissions,
+}
+
+#[derive(Debug)]
+pub struct PosixAcl {
+    pub entries: Vec<PosixAce>,
+}
+
+/// Controls how [`Acl::parse`] handles anomalies in its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Abort with an error on the first anomaly.
+    Strict,
+    /// Collect anomalies as warnings and skip the offending token or line.
+    Lenient,
+}
+
+/// An anomaly encountered while parsing the `type:flags:principal:permissions`
+/// text format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AclParseError {
+    UnknownAceType(char),
+    UnknownFlag(char),
+    UnknownPermission(char),
+    MalformedLine { line: String, field_count: usize },
+}
+
+impl fmt::Display for AclParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AclParseError::UnknownAceType(c) => write!(f, "Unknown ACE type: {c}"),
+            AclParseError::UnknownFlag(c) => write!(f, "Unknown ACE flag: {c}"),
+            AclParseError::UnknownPermission(c) => write!(f, "Unknown ACE permission: {c}"),
+            AclParseError::MalformedLine { line, field_count } => write!(
+                f,
+                "Malformed ACE line (expected 4 fields, found {field_count}): {line}"
+            ),
         }
-        let path_input = path.as_ref().to_str().unwrap_or_default();
-        let output = Command::new("nfs4_getfacl").arg(path_input).output()?;
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let lines: Vec<&str> = output_str.lines().collect();
+    }
+}
+
+impl std::error::Error for AclParseError {}
+
+impl Acl {
+    /// Parses ACL lines in the `type:flags:principal:permissions` text
+    /// format emitted by `nfs4_getfacl`. In `ParseMode::Strict` the first
+    /// anomaly aborts with an error; in `ParseMode::Lenient` anomalies are
+    /// collected as warnings and the offending token (or line) is skipped.
+    pub fn parse(
+        input: &str,
+        mode: ParseMode,
+    ) -> std::result::Result<(Acl, Vec<AclParseError>), AclParseError> {
         let mut aces: Vec<Ace> = Vec::new();
-        for line in lines {
-            if line.is_empty() || line.starts_with("#") {
+        let mut warnings: Vec<AclParseError> = Vec::new();
+
+        for line in input.lines() {
+            if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
-            let parts: Vec<&str> = line.split(":").collect();
+            let parts: Vec<&str> = line.split(':').collect();
             if parts.len() != 4 {
-                println!("Invalid line: {}", line);
+                let error = AclParseError::MalformedLine {
+                    line: line.to_string(),
+                    field_count: parts.len(),
+                };
+                match mode {
+                    ParseMode::Strict => return Err(error),
+                    ParseMode::Lenient => {
+                        warnings.push(error);
+                        continue;
+                    }
+                }
             }
 
-            let ace_type = parts[0].to_string().chars().last().unwrap();
-            let ace_flags = parts[1].to_string();
-            let ace_principals = parts[2].to_string();
-            let ace_permissions = parts[3].to_string();
-
+            let ace_type = parts[0].chars().last().unwrap_or_default();
             let type_ = match ace_type {
                 'A' => AceType::Allow,
                 'D' => AceType::Deny,
                 'U' => AceType::Audit,
                 'L' => AceType::Alarm,
-                _ => unreachable!("Invalid type: {}", ace_type),
+                _ => {
+                    let error = AclParseError::UnknownAceType(ace_type);
+                    match mode {
+                        ParseMode::Strict => return Err(error),
+                        ParseMode::Lenient => {
+                            warnings.push(error);
+                            continue;
+                        }
+                    }
+                }
             };
 
             let mut flags = AceFlags::empty();
-            for char in ace_flags.chars() {
+            for char in parts[1].chars() {
                 match char {
                     'g' => flags |= AceFlags::GROUP,
                     'd' => flags |= AceFlags::DIRECTORY_INHERIT,
@@ -116,16 +203,22 @@ impl Acl {
                     'S' => flags |= AceFlags::SUCCESSFUL_ACCESS,
                     'F' => flags |= AceFlags::FAILED_ACCESS,
                     _ => {
-                        println!("Invalid flag: {}", char);
-                        break;
+                        let error = AclParseError::UnknownFlag(char);
+                        match mode {
+                            ParseMode::Strict => return Err(error),
+                            ParseMode::Lenient => {
+                                warnings.push(error);
+                                continue;
+                            }
+                        }
                     }
                 }
             }
 
-            let principals = AcePrincipals(ace_principals);
+            let principals = AcePrincipals(parts[2].to_string());
 
             let mut permissions = AcePermissions::empty();
-            for char in ace_permissions.chars() {
+            for char in parts[3].chars() {
                 match char {
                     'r' => permissions |= AcePermissions::READ_DATA,
                     'w' => permissions |= AcePermissions::WRITE_DATA,
@@ -142,21 +235,42 @@ impl Acl {
                     'o' => permissions |= AcePermissions::WRITE_OWNER,
                     'y' => permissions |= AcePermissions::SYNCHRONIZE,
                     _ => {
-                        println!("Invalid permission: {}", char);
-                        break;
+                        let error = AclParseError::UnknownPermission(char);
+                        match mode {
+                            ParseMode::Strict => return Err(error),
+                            ParseMode::Lenient => {
+                                warnings.push(error);
+                                continue;
+                            }
+                        }
                     }
                 }
             }
 
-            let ace = Ace {
+            aces.push(Ace {
                 ace_type: type_,
                 ace_flags: flags,
                 ace_principals: principals,
                 ace_permissions: permissions,
-            };
-            aces.push(ace);
+            });
         }
-        let acl = Acl { aces: aces };
+
+        Ok((Acl { aces }, warnings))
+    }
+
+    /// Parses the output of the nfs4_getfacl command to a Acl
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Acl> {
+        if !path.as_ref().exists() {
+            return Err(Error::new(
+                std::io::ErrorKind::NotFound,
+                "The file does not exist",
+            ));
+        }
+        let path_input = path.as_ref().to_str().unwrap_or_default();
+        let output = Command::new("nfs4_getfacl").arg(path_input).output()?;
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let (acl, _warnings) = Acl::parse(&output_str, ParseMode::Lenient)
+            .map_err(|error| Error::new(std::io::ErrorKind::InvalidData, error.to_string()))?;
 
         Ok(acl)
     }
@@ -174,4 +288,849 @@ impl Acl {
 
         group_aces
     }
+
+    /// Renders the ACL back to the `type:flags:principal:permissions` text
+    /// format understood by `nfs4_setfacl`.
+    pub fn to_nfs4_string(&self) -> String {
+        self.aces
+            .iter()
+            .map(|ace| ace.to_nfs4_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Writes this ACL to `path` by piping its textual form into
+    /// `nfs4_setfacl -s -`.
+    pub fn apply_to_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path_input = path.as_ref().to_str().unwrap_or_default();
+        let mut child = Command::new("nfs4_setfacl")
+            .arg("-s")
+            .arg("-")
+            .arg(path_input)
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        let mut stdin = child.stdin.take().ok_or_else(|| {
+            Error::new(std::io::ErrorKind::BrokenPipe, "Failed to open stdin")
+        })?;
+        let mut acl_text = self.to_nfs4_string();
+        acl_text.push('\n');
+        stdin.write_all(acl_text.as_bytes())?;
+        drop(stdin);
+
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(Error::other("nfs4_setfacl exited with a non-zero status"));
+        }
+
+        Ok(())
+    }
+
+    /// Appends an ACE to the end of the ACL.
+    pub fn add_ace(&mut self, ace: Ace) {
+        self.aces.push(ace);
+    }
+
+    /// Removes and returns the ACE at `index`.
+    pub fn remove_ace(&mut self, index: usize) -> Result<Ace> {
+        if index >= self.aces.len() {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("ACE index {index} out of bounds"),
+            ));
+        }
+        Ok(self.aces.remove(index))
+    }
+
+    /// Replaces the ACE at `index`.
+    pub fn set_ace(&mut self, index: usize, ace: Ace) -> Result<()> {
+        match self.aces.get_mut(index) {
+            Some(slot) => {
+                *slot = ace;
+                Ok(())
+            }
+            None => Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("ACE index {index} out of bounds"),
+            )),
+        }
+    }
+
+    /// Reads and decodes the `system.nfs4_acl` xattr of `path` directly via
+    /// `getxattr`, without spawning `nfs4_getfacl`.
+    #[cfg(feature = "xattr")]
+    pub fn from_xattr<P: AsRef<Path>>(path: P) -> Result<Acl> {
+        xattr::read_acl(path)
+    }
+
+    /// Encodes this ACL to the wire format and writes it to the
+    /// `system.nfs4_acl` xattr of `path` directly via `setxattr`, without
+    /// spawning `nfs4_setfacl`.
+    #[cfg(feature = "xattr")]
+    pub fn apply_via_xattr<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        xattr::write_acl(path, self)
+    }
+
+    /// Derives the ACL a new child would inherit from this (parent
+    /// directory's) ACL, following NFSv4 inheritance rules. `is_dir`
+    /// selects whether the child being created is itself a directory.
+    pub fn inherit(&self, is_dir: bool) -> Acl {
+        let inheritable_flag = if is_dir {
+            AceFlags::DIRECTORY_INHERIT
+        } else {
+            AceFlags::FILE_INHERIT
+        };
+
+        let aces = self
+            .aces
+            .iter()
+            .filter(|ace| ace.ace_flags.contains(inheritable_flag))
+            .map(|ace| {
+                let mut flags = ace.ace_flags;
+                flags.remove(AceFlags::INHERIT_ONLY);
+
+                if !is_dir || flags.contains(AceFlags::NO_PROPAGATE_INHERIT) {
+                    flags.remove(
+                        AceFlags::FILE_INHERIT
+                            | AceFlags::DIRECTORY_INHERIT
+                            | AceFlags::NO_PROPAGATE_INHERIT
+                            | AceFlags::INHERIT_ONLY,
+                    );
+                }
+
+                Ace {
+                    ace_type: ace.ace_type,
+                    ace_flags: flags,
+                    ace_principals: ace.ace_principals.clone(),
+                    ace_permissions: ace.ace_permissions,
+                }
+            })
+            .collect();
+
+        Acl { aces }
+    }
+
+    /// Evaluates whether `user` (a member of `groups`) is granted `requested`
+    /// access, following the ordered NFSv4 ACL evaluation algorithm: ACEs are
+    /// walked in order, a matching `Deny` entry immediately rejects if it
+    /// covers any still-outstanding bit, matching `Allow` entries clear bits
+    /// from the still-outstanding mask, and access is
+    /// granted only once every requested bit has been explicitly allowed.
+    pub fn access(&self, user: &str, groups: &[&str], requested: AcePermissions) -> bool {
+        let mut remaining = requested;
+
+        for ace in &self.aces {
+            if remaining.is_empty() {
+                return true;
+            }
+
+            if ace.ace_type != AceType::Allow && ace.ace_type != AceType::Deny {
+                continue;
+            }
+
+            if ace.ace_flags.contains(AceFlags::INHERIT_ONLY) {
+                continue;
+            }
+
+            if !ace_matches_requester(ace, user, groups) {
+                continue;
+            }
+
+            match ace.ace_type {
+                AceType::Deny => {
+                    if remaining.intersects(ace.ace_permissions) {
+                        return false;
+                    }
+                }
+                AceType::Allow => {
+                    remaining.remove(ace.ace_permissions);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        remaining.is_empty()
+    }
+
+    /// Folds this NFSv4 ACL down to a classic POSIX rwx ACL, the way the
+    /// kernel's nfs4acl code does: `Allow`/`Deny` entries for each principal
+    /// are applied in order to an accumulated rwx set, and a `mask` entry is
+    /// synthesized as the union of every named user/group entry.
+    pub fn to_posix(&self) -> PosixAcl {
+        use std::collections::HashMap;
+
+        let mut user_obj = PosixFold::default();
+        let mut group_obj = PosixFold::default();
+        let mut other = PosixFold::default();
+        let mut named_users: HashMap<String, PosixFold> = HashMap::new();
+        let mut named_groups: HashMap<String, PosixFold> = HashMap::new();
+        let mut named_user_order: Vec<String> = Vec::new();
+        let mut named_group_order: Vec<String> = Vec::new();
+
+        for ace in &self.aces {
+            if ace.ace_type != AceType::Allow && ace.ace_type != AceType::Deny {
+                continue;
+            }
+
+            let bits = nfs4_permissions_to_posix(ace.ace_permissions);
+            let is_deny = ace.ace_type == AceType::Deny;
+
+            match ace.ace_principals.0.as_str() {
+                "OWNER@" => user_obj.apply(bits, is_deny),
+                "GROUP@" => group_obj.apply(bits, is_deny),
+                "EVERYONE@" => other.apply(bits, is_deny),
+                principal if ace.ace_flags.contains(AceFlags::GROUP) => {
+                    if !named_groups.contains_key(principal) {
+                        named_group_order.push(principal.to_string());
+                    }
+                    named_groups
+                        .entry(principal.to_string())
+                        .or_default()
+                        .apply(bits, is_deny);
+                }
+                principal => {
+                    if !named_users.contains_key(principal) {
+                        named_user_order.push(principal.to_string());
+                    }
+                    named_users
+                        .entry(principal.to_string())
+                        .or_default()
+                        .apply(bits, is_deny);
+                }
+            }
+        }
+
+        let mut mask = PosixPermissions::empty();
+        for fold in named_users.values().chain(named_groups.values()) {
+            mask |= fold.granted;
+        }
+
+        let mut entries = vec![PosixAce {
+            tag: PosixAclTag::UserObj,
+            id: None,
+            permissions: user_obj.granted,
+        }];
+        for principal in &named_user_order {
+            entries.push(PosixAce {
+                tag: PosixAclTag::NamedUser,
+                id: Some(principal.clone()),
+                permissions: named_users[principal].granted,
+            });
+        }
+        entries.push(PosixAce {
+            tag: PosixAclTag::GroupObj,
+            id: None,
+            permissions: group_obj.granted,
+        });
+        for principal in &named_group_order {
+            entries.push(PosixAce {
+                tag: PosixAclTag::NamedGroup,
+                id: Some(principal.clone()),
+                permissions: named_groups[principal].granted,
+            });
+        }
+        entries.push(PosixAce {
+            tag: PosixAclTag::Mask,
+            id: None,
+            permissions: mask,
+        });
+        entries.push(PosixAce {
+            tag: PosixAclTag::Other,
+            id: None,
+            permissions: other.granted,
+        });
+
+        PosixAcl { entries }
+    }
+
+    /// Expands a classic POSIX rwx ACL back into an NFSv4 ACL, emitting an
+    /// ordered `Deny`/`Allow` ACE pair per entry whenever the ACL's `mask`
+    /// restricts a named user/group entry, so the effective permissions
+    /// match. Every `Allow` ACE is also granted `READ_ACL`/`READ_ATTRIBUTES`/
+    /// `SYNCHRONIZE`, per convention.
+    pub fn from_posix(posix: &PosixAcl) -> Acl {
+        let mask = posix
+            .entries
+            .iter()
+            .find(|entry| entry.tag == PosixAclTag::Mask)
+            .map(|entry| entry.permissions)
+            .unwrap_or(PosixPermissions::all());
+
+        let mut aces = Vec::new();
+        for entry in &posix.entries {
+            if entry.tag == PosixAclTag::Mask {
+                continue;
+            }
+
+            let principal = match &entry.tag {
+                PosixAclTag::UserObj => "OWNER@".to_string(),
+                PosixAclTag::GroupObj => "GROUP@".to_string(),
+                PosixAclTag::Other => "EVERYONE@".to_string(),
+                PosixAclTag::NamedUser | PosixAclTag::NamedGroup => {
+                    entry.id.clone().unwrap_or_default()
+                }
+                PosixAclTag::Mask => unreachable!(),
+            };
+
+            let flags = if entry.tag == PosixAclTag::NamedGroup {
+                AceFlags::GROUP
+            } else {
+                AceFlags::empty()
+            };
+
+            let effective = match entry.tag {
+                PosixAclTag::NamedUser | PosixAclTag::NamedGroup => entry.permissions & mask,
+                _ => entry.permissions,
+            };
+
+            let denied = entry.permissions.difference(effective);
+            if !denied.is_empty() {
+                aces.push(Ace {
+                    ace_type: AceType::Deny,
+                    ace_flags: flags,
+                    ace_principals: AcePrincipals(principal.clone()),
+                    ace_permissions: posix_bits_to_nfs4(denied),
+                });
+            }
+
+            aces.push(Ace {
+                ace_type: AceType::Allow,
+                ace_flags: flags,
+                ace_principals: AcePrincipals(principal),
+                ace_permissions: posix_bits_to_nfs4(effective)
+                    | AcePermissions::READ_ACL
+                    | AcePermissions::READ_ATTRIBUTES
+                    | AcePermissions::SYNCHRONIZE,
+            });
+        }
+
+        Acl { aces }
+    }
+}
+
+/// Folds NFSv4 permission bits into their POSIX rwx equivalents.
+fn nfs4_permissions_to_posix(permissions: AcePermissions) -> PosixPermissions {
+    let mut bits = PosixPermissions::empty();
+    if permissions.intersects(AcePermissions::READ_DATA | AcePermissions::READ_NAMED_ATTRIBUTES) {
+        bits |= PosixPermissions::READ;
+    }
+    if permissions.intersects(AcePermissions::WRITE_DATA | AcePermissions::APPEND_DATA) {
+        bits |= PosixPermissions::WRITE;
+    }
+    if permissions.contains(AcePermissions::EXECUTE) {
+        bits |= PosixPermissions::EXECUTE;
+    }
+    bits
+}
+
+/// Expands POSIX rwx bits into the NFSv4 permission bits they cover.
+fn posix_bits_to_nfs4(bits: PosixPermissions) -> AcePermissions {
+    let mut permissions = AcePermissions::empty();
+    if bits.contains(PosixPermissions::READ) {
+        permissions |= AcePermissions::READ_DATA | AcePermissions::READ_NAMED_ATTRIBUTES;
+    }
+    if bits.contains(PosixPermissions::WRITE) {
+        permissions |= AcePermissions::WRITE_DATA | AcePermissions::APPEND_DATA;
+    }
+    if bits.contains(PosixPermissions::EXECUTE) {
+        permissions |= AcePermissions::EXECUTE;
+    }
+    permissions
+}
+
+/// Accumulates the effective POSIX permissions granted to a single principal
+/// as its NFSv4 ACEs are folded in order. Mirrors `Acl::access`'s "first
+/// applicable entry decides" rule per bit: once an ACE has ruled on a given
+/// bit (by allowing or denying it), later ACEs no longer affect that bit.
+struct PosixFold {
+    granted: PosixPermissions,
+    decided: PosixPermissions,
+}
+
+impl Default for PosixFold {
+    fn default() -> Self {
+        PosixFold {
+            granted: PosixPermissions::empty(),
+            decided: PosixPermissions::empty(),
+        }
+    }
+}
+
+impl PosixFold {
+    fn apply(&mut self, bits: PosixPermissions, is_deny: bool) {
+        let undecided = bits.difference(self.decided);
+        if undecided.is_empty() {
+            return;
+        }
+        if !is_deny {
+            self.granted.insert(undecided);
+        }
+        self.decided.insert(undecided);
+    }
+}
+
+/// Determines whether `ace` applies to `user`/`groups`, per the special
+/// `OWNER@`/`GROUP@`/`EVERYONE@` principals and named user/group matching.
+fn ace_matches_requester(ace: &Ace, user: &str, groups: &[&str]) -> bool {
+    match ace.ace_principals.0.as_str() {
+        "OWNER@" | "GROUP@" | "EVERYONE@" => true,
+        principal => {
+            if ace.ace_flags.contains(AceFlags::GROUP) {
+                groups.contains(&principal)
+            } else {
+                principal == user
+            }
+        }
+    }
+}
+
+impl fmt::Display for Acl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_nfs4_string())
+    }
+}
+
+impl Ace {
+    /// Renders this ACE back to its `type:flags:principal:permissions` text form.
+    pub fn to_nfs4_string(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            ace_type_to_char(self.ace_type),
+            ace_flags_to_string(self.ace_flags),
+            self.ace_principals.0,
+            ace_permissions_to_string(self.ace_permissions)
+        )
+    }
+}
+
+impl fmt::Display for Ace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_nfs4_string())
+    }
+}
+
+fn ace_type_to_char(ace_type: AceType) -> char {
+    match ace_type {
+        AceType::Allow => 'A',
+        AceType::Deny => 'D',
+        AceType::Audit => 'U',
+        AceType::Alarm => 'L',
+    }
+}
+
+fn ace_flags_to_string(flags: AceFlags) -> String {
+    let mut out = String::new();
+    if flags.contains(AceFlags::GROUP) {
+        out.push('g');
+    }
+    if flags.contains(AceFlags::DIRECTORY_INHERIT) {
+        out.push('d');
+    }
+    if flags.contains(AceFlags::FILE_INHERIT) {
+        out.push('f');
+    }
+    if flags.contains(AceFlags::NO_PROPAGATE_INHERIT) {
+        out.push('n');
+    }
+    if flags.contains(AceFlags::INHERIT_ONLY) {
+        out.push('i');
+    }
+    if flags.contains(AceFlags::SUCCESSFUL_ACCESS) {
+        out.push('S');
+    }
+    if flags.contains(AceFlags::FAILED_ACCESS) {
+        out.push('F');
+    }
+    out
+}
+
+fn ace_permissions_to_string(permissions: AcePermissions) -> String {
+    let mut out = String::new();
+    if permissions.contains(AcePermissions::READ_DATA) {
+        out.push('r');
+    }
+    if permissions.contains(AcePermissions::WRITE_DATA) {
+        out.push('w');
+    }
+    if permissions.contains(AcePermissions::APPEND_DATA) {
+        out.push('a');
+    }
+    if permissions.contains(AcePermissions::EXECUTE) {
+        out.push('x');
+    }
+    if permissions.contains(AcePermissions::DELETE) {
+        out.push('d');
+    }
+    if permissions.contains(AcePermissions::DELETE_CHILD) {
+        out.push('D');
+    }
+    if permissions.contains(AcePermissions::READ_ATTRIBUTES) {
+        out.push('t');
+    }
+    if permissions.contains(AcePermissions::WRITE_ATTRIBUTES) {
+        out.push('T');
+    }
+    if permissions.contains(AcePermissions::READ_NAMED_ATTRIBUTES) {
+        out.push('n');
+    }
+    if permissions.contains(AcePermissions::WRITE_NAMED_ATTRIBUTES) {
+        out.push('N');
+    }
+    if permissions.contains(AcePermissions::READ_ACL) {
+        out.push('c');
+    }
+    if permissions.contains(AcePermissions::WRITE_ACL) {
+        out.push('C');
+    }
+    if permissions.contains(AcePermissions::WRITE_OWNER) {
+        out.push('o');
+    }
+    if permissions.contains(AcePermissions::SYNCHRONIZE) {
+        out.push('y');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ace(
+        ace_type: AceType,
+        flags: AceFlags,
+        principal: &str,
+        permissions: AcePermissions,
+    ) -> Ace {
+        Ace {
+            ace_type,
+            ace_flags: flags,
+            ace_principals: AcePrincipals(principal.to_string()),
+            ace_permissions: permissions,
+        }
+    }
+
+    #[test]
+    fn access_grants_when_allow_covers_requested_bits() {
+        let acl = Acl {
+            aces: vec![ace(
+                AceType::Allow,
+                AceFlags::empty(),
+                "OWNER@",
+                AcePermissions::READ_DATA | AcePermissions::WRITE_DATA,
+            )],
+        };
+
+        assert!(acl.access("alice", &[], AcePermissions::READ_DATA));
+    }
+
+    #[test]
+    fn access_denies_by_default_with_no_matching_entry() {
+        let acl = Acl { aces: vec![] };
+
+        assert!(!acl.access("alice", &[], AcePermissions::READ_DATA));
+    }
+
+    #[test]
+    fn access_first_matching_entry_decides_the_bit() {
+        // Allow comes first, so it decides READ_DATA; the later Deny for
+        // the same bit is never consulted.
+        let acl = Acl {
+            aces: vec![
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+                ace(
+                    AceType::Deny,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+            ],
+        };
+
+        assert!(acl.access("alice", &[], AcePermissions::READ_DATA));
+    }
+
+    #[test]
+    fn access_deny_before_allow_rejects_immediately() {
+        let acl = Acl {
+            aces: vec![
+                ace(
+                    AceType::Deny,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+            ],
+        };
+
+        assert!(!acl.access("alice", &[], AcePermissions::READ_DATA));
+    }
+
+    #[test]
+    fn access_ignores_inherit_only_and_audit_alarm_entries() {
+        let acl = Acl {
+            aces: vec![
+                ace(
+                    AceType::Audit,
+                    AceFlags::empty(),
+                    "EVERYONE@",
+                    AcePermissions::READ_DATA,
+                ),
+                ace(
+                    AceType::Deny,
+                    AceFlags::INHERIT_ONLY,
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "EVERYONE@",
+                    AcePermissions::READ_DATA,
+                ),
+            ],
+        };
+
+        assert!(acl.access("alice", &[], AcePermissions::READ_DATA));
+    }
+
+    #[test]
+    fn access_matches_named_group_membership() {
+        let acl = Acl {
+            aces: vec![ace(
+                AceType::Allow,
+                AceFlags::GROUP,
+                "staff",
+                AcePermissions::WRITE_DATA,
+            )],
+        };
+
+        assert!(acl.access("alice", &["staff"], AcePermissions::WRITE_DATA));
+        assert!(!acl.access("bob", &["eng"], AcePermissions::WRITE_DATA));
+    }
+
+    fn posix_permissions(entries: &[PosixAce], tag: PosixAclTag, id: Option<&str>) -> PosixPermissions {
+        entries
+            .iter()
+            .find(|entry| entry.tag == tag && entry.id.as_deref() == id)
+            .unwrap_or_else(|| panic!("no {tag:?}/{id:?} entry in {entries:?}"))
+            .permissions
+    }
+
+    #[test]
+    fn to_posix_agrees_with_access_on_first_matching_entry() {
+        // Same ACL as access_first_matching_entry_decides_the_bit: the
+        // Allow comes first, so alice is readable even though a Deny for
+        // the same bit follows.
+        let acl = Acl {
+            aces: vec![
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+                ace(
+                    AceType::Deny,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+            ],
+        };
+
+        assert!(acl.access("alice", &[], AcePermissions::READ_DATA));
+
+        let posix = acl.to_posix();
+        let alice_perms = posix_permissions(&posix.entries, PosixAclTag::NamedUser, Some("alice"));
+        assert!(alice_perms.contains(PosixPermissions::READ));
+    }
+
+    #[test]
+    fn to_posix_deny_before_allow_withholds_the_bit() {
+        let acl = Acl {
+            aces: vec![
+                ace(
+                    AceType::Deny,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "alice",
+                    AcePermissions::READ_DATA,
+                ),
+            ],
+        };
+
+        let posix = acl.to_posix();
+        let alice_perms = posix_permissions(&posix.entries, PosixAclTag::NamedUser, Some("alice"));
+        assert!(!alice_perms.contains(PosixPermissions::READ));
+    }
+
+    #[test]
+    fn to_posix_folds_owner_group_everyone() {
+        let acl = Acl {
+            aces: vec![
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "OWNER@",
+                    AcePermissions::READ_DATA | AcePermissions::WRITE_DATA,
+                ),
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "GROUP@",
+                    AcePermissions::READ_DATA,
+                ),
+                ace(
+                    AceType::Allow,
+                    AceFlags::empty(),
+                    "EVERYONE@",
+                    AcePermissions::EXECUTE,
+                ),
+            ],
+        };
+
+        let posix = acl.to_posix();
+        assert_eq!(
+            posix_permissions(&posix.entries, PosixAclTag::UserObj, None),
+            PosixPermissions::READ | PosixPermissions::WRITE
+        );
+        assert_eq!(
+            posix_permissions(&posix.entries, PosixAclTag::GroupObj, None),
+            PosixPermissions::READ
+        );
+        assert_eq!(
+            posix_permissions(&posix.entries, PosixAclTag::Other, None),
+            PosixPermissions::EXECUTE
+        );
+    }
+
+    #[test]
+    fn posix_roundtrip_preserves_effective_permissions() {
+        let posix = PosixAcl {
+            entries: vec![
+                PosixAce {
+                    tag: PosixAclTag::UserObj,
+                    id: None,
+                    permissions: PosixPermissions::READ | PosixPermissions::WRITE,
+                },
+                PosixAce {
+                    tag: PosixAclTag::NamedUser,
+                    id: Some("alice".to_string()),
+                    permissions: PosixPermissions::READ | PosixPermissions::WRITE,
+                },
+                PosixAce {
+                    tag: PosixAclTag::GroupObj,
+                    id: None,
+                    permissions: PosixPermissions::READ,
+                },
+                PosixAce {
+                    tag: PosixAclTag::Mask,
+                    id: None,
+                    permissions: PosixPermissions::READ,
+                },
+                PosixAce {
+                    tag: PosixAclTag::Other,
+                    id: None,
+                    permissions: PosixPermissions::empty(),
+                },
+            ],
+        };
+
+        let acl = Acl::from_posix(&posix);
+        let roundtripped = acl.to_posix();
+
+        // The mask restricts alice to READ even though her entry also
+        // grants WRITE; the round trip must reflect the restricted,
+        // effective permission, not the raw entry.
+        assert_eq!(
+            posix_permissions(&roundtripped.entries, PosixAclTag::NamedUser, Some("alice")),
+            PosixPermissions::READ
+        );
+        assert_eq!(
+            posix_permissions(&roundtripped.entries, PosixAclTag::UserObj, None),
+            PosixPermissions::READ | PosixPermissions::WRITE
+        );
+    }
+
+    #[test]
+    fn inherit_drops_entries_without_an_applicable_inherit_flag() {
+        let acl = Acl {
+            aces: vec![ace(
+                AceType::Allow,
+                AceFlags::empty(),
+                "OWNER@",
+                AcePermissions::READ_DATA,
+            )],
+        };
+
+        assert!(acl.inherit(true).aces.is_empty());
+        assert!(acl.inherit(false).aces.is_empty());
+    }
+
+    #[test]
+    fn inherit_file_strips_all_inherit_flags() {
+        let acl = Acl {
+            aces: vec![ace(
+                AceType::Allow,
+                AceFlags::FILE_INHERIT | AceFlags::DIRECTORY_INHERIT | AceFlags::INHERIT_ONLY,
+                "OWNER@",
+                AcePermissions::READ_DATA,
+            )],
+        };
+
+        let child = acl.inherit(false);
+        assert_eq!(child.aces.len(), 1);
+        assert_eq!(child.aces[0].ace_flags, AceFlags::empty());
+    }
+
+    #[test]
+    fn inherit_directory_propagates_inherit_flags() {
+        let acl = Acl {
+            aces: vec![ace(
+                AceType::Allow,
+                AceFlags::FILE_INHERIT | AceFlags::DIRECTORY_INHERIT,
+                "OWNER@",
+                AcePermissions::READ_DATA,
+            )],
+        };
+
+        let child = acl.inherit(true);
+        assert_eq!(child.aces.len(), 1);
+        assert_eq!(
+            child.aces[0].ace_flags,
+            AceFlags::FILE_INHERIT | AceFlags::DIRECTORY_INHERIT
+        );
+    }
+
+    #[test]
+    fn inherit_directory_with_no_propagate_strips_inherit_flags() {
+        let acl = Acl {
+            aces: vec![ace(
+                AceType::Allow,
+                AceFlags::DIRECTORY_INHERIT | AceFlags::NO_PROPAGATE_INHERIT,
+                "OWNER@",
+                AcePermissions::READ_DATA,
+            )],
+        };
+
+        let child = acl.inherit(true);
+        assert_eq!(child.aces.len(), 1);
+        assert_eq!(child.aces[0].ace_flags, AceFlags::empty());
+    }
 }
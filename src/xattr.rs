@@ -0,0 +1,380 @@
+//! Reads and writes NFSv4 ACLs directly through the `system.nfs4_acl`
+//! extended attribute, bypassing the `nfs4_getfacl`/`nfs4_setfacl`
+//! subprocesses entirely. Enabled by the `xattr` cargo feature.
+
+use crate::{Ace, AceFlags, AcePermissions, AcePrincipals, AceType, Acl};
+use rustix::fs::{getxattr, setxattr, XattrFlags};
+use std::{
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+const NFS4_ACL_XATTR: &str = "system.nfs4_acl";
+
+// ACE type constants, as defined by the NFSv4 protocol (RFC 7530 §6.2.1).
+const ACE4_ACCESS_ALLOWED_ACE_TYPE: u32 = 0x0000_0000;
+const ACE4_ACCESS_DENIED_ACE_TYPE: u32 = 0x0000_0001;
+const ACE4_SYSTEM_AUDIT_ACE_TYPE: u32 = 0x0000_0002;
+const ACE4_SYSTEM_ALARM_ACE_TYPE: u32 = 0x0000_0003;
+
+// ACE flag bits.
+const ACE4_FILE_INHERIT_ACE: u32 = 0x0000_0001;
+const ACE4_DIRECTORY_INHERIT_ACE: u32 = 0x0000_0002;
+const ACE4_NO_PROPAGATE_INHERIT_ACE: u32 = 0x0000_0004;
+const ACE4_INHERIT_ONLY_ACE: u32 = 0x0000_0008;
+const ACE4_SUCCESSFUL_ACCESS_ACE_FLAG: u32 = 0x0000_0010;
+const ACE4_FAILED_ACCESS_ACE_FLAG: u32 = 0x0000_0020;
+const ACE4_IDENTIFIER_GROUP: u32 = 0x0000_0040;
+
+// ACE access-mask bits.
+const ACE4_READ_DATA: u32 = 0x0000_0001;
+const ACE4_WRITE_DATA: u32 = 0x0000_0002;
+const ACE4_APPEND_DATA: u32 = 0x0000_0004;
+const ACE4_READ_NAMED_ATTRS: u32 = 0x0000_0008;
+const ACE4_WRITE_NAMED_ATTRS: u32 = 0x0000_0010;
+const ACE4_EXECUTE: u32 = 0x0000_0020;
+const ACE4_DELETE_CHILD: u32 = 0x0000_0040;
+const ACE4_READ_ATTRIBUTES: u32 = 0x0000_0080;
+const ACE4_WRITE_ATTRIBUTES: u32 = 0x0000_0100;
+const ACE4_DELETE: u32 = 0x0001_0000;
+const ACE4_READ_ACL: u32 = 0x0002_0000;
+const ACE4_WRITE_ACL: u32 = 0x0004_0000;
+const ACE4_WRITE_OWNER: u32 = 0x0008_0000;
+const ACE4_SYNCHRONIZE: u32 = 0x0010_0000;
+
+/// Reads and decodes the `system.nfs4_acl` xattr of `path` into an [`Acl`].
+pub fn read_acl<P: AsRef<Path>>(path: P) -> Result<Acl> {
+    let mut buf = vec![0u8; 4096];
+    loop {
+        match getxattr(path.as_ref(), NFS4_ACL_XATTR, &mut buf) {
+            Ok(len) => return decode_acl(&buf[..len]),
+            Err(rustix::io::Errno::RANGE) => {
+                buf.resize(buf.len() * 2, 0);
+            }
+            Err(err) => return Err(Error::from(err)),
+        }
+    }
+}
+
+/// Encodes `acl` to the wire format and writes it to the `system.nfs4_acl`
+/// xattr of `path`.
+pub fn write_acl<P: AsRef<Path>>(path: P, acl: &Acl) -> Result<()> {
+    let encoded = encode_acl(acl);
+    setxattr(
+        path.as_ref(),
+        NFS4_ACL_XATTR,
+        &encoded,
+        XattrFlags::empty(),
+    )
+    .map_err(Error::from)
+}
+
+/// Each ACE's fixed-size fields (type, flags, access mask, principal-string
+/// length) take at least this many bytes before any principal text.
+const MIN_ACE_BYTES: usize = 16;
+
+fn decode_acl(bytes: &[u8]) -> Result<Acl> {
+    let mut cursor = 0usize;
+    let count = read_u32(bytes, &mut cursor)? as usize;
+
+    let remaining = bytes.len().saturating_sub(cursor);
+    let required = count.checked_mul(MIN_ACE_BYTES).ok_or_else(|| {
+        Error::new(ErrorKind::InvalidData, "nfs4_acl xattr ACE count overflows")
+    })?;
+    if required > remaining {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "nfs4_acl xattr claims {count} ACEs but only {remaining} bytes remain"
+            ),
+        ));
+    }
+
+    let mut aces = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let ace_type = decode_type(read_u32(bytes, &mut cursor)?)?;
+        let ace_flags = decode_flags(read_u32(bytes, &mut cursor)?);
+        let ace_permissions = decode_permissions(read_u32(bytes, &mut cursor)?);
+        let principal_len = read_u32(bytes, &mut cursor)? as usize;
+        let principal = read_padded_string(bytes, &mut cursor, principal_len)?;
+
+        aces.push(Ace {
+            ace_type,
+            ace_flags,
+            ace_principals: AcePrincipals(principal),
+            ace_permissions,
+        });
+    }
+
+    Ok(Acl { aces })
+}
+
+fn encode_acl(acl: &Acl) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(acl.aces.len() as u32).to_be_bytes());
+
+    for ace in &acl.aces {
+        out.extend_from_slice(&encode_type(ace.ace_type).to_be_bytes());
+        out.extend_from_slice(&encode_flags(ace.ace_flags).to_be_bytes());
+        out.extend_from_slice(&encode_permissions(ace.ace_permissions).to_be_bytes());
+
+        let principal = ace.ace_principals.0.as_bytes();
+        out.extend_from_slice(&(principal.len() as u32).to_be_bytes());
+        out.extend_from_slice(principal);
+        let padding = (4 - (principal.len() % 4)) % 4;
+        out.resize(out.len() + padding, 0);
+    }
+
+    out
+}
+
+fn read_u32(bytes: &[u8], cursor: &mut usize) -> Result<u32> {
+    let end = *cursor + 4;
+    let word = bytes
+        .get(*cursor..end)
+        .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated nfs4_acl xattr"))?;
+    *cursor = end;
+    Ok(u32::from_be_bytes(word.try_into().unwrap()))
+}
+
+fn read_padded_string(bytes: &[u8], cursor: &mut usize, len: usize) -> Result<String> {
+    let end = *cursor + len;
+    let raw = bytes
+        .get(*cursor..end)
+        .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated nfs4_acl xattr"))?;
+    let principal = String::from_utf8_lossy(raw).into_owned();
+    let padding = (4 - (len % 4)) % 4;
+    *cursor = end + padding;
+    Ok(principal)
+}
+
+fn decode_type(value: u32) -> Result<AceType> {
+    match value {
+        ACE4_ACCESS_ALLOWED_ACE_TYPE => Ok(AceType::Allow),
+        ACE4_ACCESS_DENIED_ACE_TYPE => Ok(AceType::Deny),
+        ACE4_SYSTEM_AUDIT_ACE_TYPE => Ok(AceType::Audit),
+        ACE4_SYSTEM_ALARM_ACE_TYPE => Ok(AceType::Alarm),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unknown ACE type: {value:#x}"),
+        )),
+    }
+}
+
+fn encode_type(ace_type: AceType) -> u32 {
+    match ace_type {
+        AceType::Allow => ACE4_ACCESS_ALLOWED_ACE_TYPE,
+        AceType::Deny => ACE4_ACCESS_DENIED_ACE_TYPE,
+        AceType::Audit => ACE4_SYSTEM_AUDIT_ACE_TYPE,
+        AceType::Alarm => ACE4_SYSTEM_ALARM_ACE_TYPE,
+    }
+}
+
+fn decode_flags(value: u32) -> AceFlags {
+    let mut flags = AceFlags::empty();
+    if value & ACE4_IDENTIFIER_GROUP != 0 {
+        flags |= AceFlags::GROUP;
+    }
+    if value & ACE4_DIRECTORY_INHERIT_ACE != 0 {
+        flags |= AceFlags::DIRECTORY_INHERIT;
+    }
+    if value & ACE4_FILE_INHERIT_ACE != 0 {
+        flags |= AceFlags::FILE_INHERIT;
+    }
+    if value & ACE4_NO_PROPAGATE_INHERIT_ACE != 0 {
+        flags |= AceFlags::NO_PROPAGATE_INHERIT;
+    }
+    if value & ACE4_INHERIT_ONLY_ACE != 0 {
+        flags |= AceFlags::INHERIT_ONLY;
+    }
+    if value & ACE4_SUCCESSFUL_ACCESS_ACE_FLAG != 0 {
+        flags |= AceFlags::SUCCESSFUL_ACCESS;
+    }
+    if value & ACE4_FAILED_ACCESS_ACE_FLAG != 0 {
+        flags |= AceFlags::FAILED_ACCESS;
+    }
+    flags
+}
+
+fn encode_flags(flags: AceFlags) -> u32 {
+    let mut value = 0u32;
+    if flags.contains(AceFlags::GROUP) {
+        value |= ACE4_IDENTIFIER_GROUP;
+    }
+    if flags.contains(AceFlags::DIRECTORY_INHERIT) {
+        value |= ACE4_DIRECTORY_INHERIT_ACE;
+    }
+    if flags.contains(AceFlags::FILE_INHERIT) {
+        value |= ACE4_FILE_INHERIT_ACE;
+    }
+    if flags.contains(AceFlags::NO_PROPAGATE_INHERIT) {
+        value |= ACE4_NO_PROPAGATE_INHERIT_ACE;
+    }
+    if flags.contains(AceFlags::INHERIT_ONLY) {
+        value |= ACE4_INHERIT_ONLY_ACE;
+    }
+    if flags.contains(AceFlags::SUCCESSFUL_ACCESS) {
+        value |= ACE4_SUCCESSFUL_ACCESS_ACE_FLAG;
+    }
+    if flags.contains(AceFlags::FAILED_ACCESS) {
+        value |= ACE4_FAILED_ACCESS_ACE_FLAG;
+    }
+    value
+}
+
+fn decode_permissions(value: u32) -> AcePermissions {
+    let mut permissions = AcePermissions::empty();
+    if value & ACE4_READ_DATA != 0 {
+        permissions |= AcePermissions::READ_DATA;
+    }
+    if value & ACE4_WRITE_DATA != 0 {
+        permissions |= AcePermissions::WRITE_DATA;
+    }
+    if value & ACE4_APPEND_DATA != 0 {
+        permissions |= AcePermissions::APPEND_DATA;
+    }
+    if value & ACE4_EXECUTE != 0 {
+        permissions |= AcePermissions::EXECUTE;
+    }
+    if value & ACE4_DELETE != 0 {
+        permissions |= AcePermissions::DELETE;
+    }
+    if value & ACE4_DELETE_CHILD != 0 {
+        permissions |= AcePermissions::DELETE_CHILD;
+    }
+    if value & ACE4_READ_ATTRIBUTES != 0 {
+        permissions |= AcePermissions::READ_ATTRIBUTES;
+    }
+    if value & ACE4_WRITE_ATTRIBUTES != 0 {
+        permissions |= AcePermissions::WRITE_ATTRIBUTES;
+    }
+    if value & ACE4_READ_NAMED_ATTRS != 0 {
+        permissions |= AcePermissions::READ_NAMED_ATTRIBUTES;
+    }
+    if value & ACE4_WRITE_NAMED_ATTRS != 0 {
+        permissions |= AcePermissions::WRITE_NAMED_ATTRIBUTES;
+    }
+    if value & ACE4_READ_ACL != 0 {
+        permissions |= AcePermissions::READ_ACL;
+    }
+    if value & ACE4_WRITE_ACL != 0 {
+        permissions |= AcePermissions::WRITE_ACL;
+    }
+    if value & ACE4_WRITE_OWNER != 0 {
+        permissions |= AcePermissions::WRITE_OWNER;
+    }
+    if value & ACE4_SYNCHRONIZE != 0 {
+        permissions |= AcePermissions::SYNCHRONIZE;
+    }
+    permissions
+}
+
+fn encode_permissions(permissions: AcePermissions) -> u32 {
+    let mut value = 0u32;
+    if permissions.contains(AcePermissions::READ_DATA) {
+        value |= ACE4_READ_DATA;
+    }
+    if permissions.contains(AcePermissions::WRITE_DATA) {
+        value |= ACE4_WRITE_DATA;
+    }
+    if permissions.contains(AcePermissions::APPEND_DATA) {
+        value |= ACE4_APPEND_DATA;
+    }
+    if permissions.contains(AcePermissions::EXECUTE) {
+        value |= ACE4_EXECUTE;
+    }
+    if permissions.contains(AcePermissions::DELETE) {
+        value |= ACE4_DELETE;
+    }
+    if permissions.contains(AcePermissions::DELETE_CHILD) {
+        value |= ACE4_DELETE_CHILD;
+    }
+    if permissions.contains(AcePermissions::READ_ATTRIBUTES) {
+        value |= ACE4_READ_ATTRIBUTES;
+    }
+    if permissions.contains(AcePermissions::WRITE_ATTRIBUTES) {
+        value |= ACE4_WRITE_ATTRIBUTES;
+    }
+    if permissions.contains(AcePermissions::READ_NAMED_ATTRIBUTES) {
+        value |= ACE4_READ_NAMED_ATTRS;
+    }
+    if permissions.contains(AcePermissions::WRITE_NAMED_ATTRIBUTES) {
+        value |= ACE4_WRITE_NAMED_ATTRS;
+    }
+    if permissions.contains(AcePermissions::READ_ACL) {
+        value |= ACE4_READ_ACL;
+    }
+    if permissions.contains(AcePermissions::WRITE_ACL) {
+        value |= ACE4_WRITE_ACL;
+    }
+    if permissions.contains(AcePermissions::WRITE_OWNER) {
+        value |= ACE4_WRITE_OWNER;
+    }
+    if permissions.contains(AcePermissions::SYNCHRONIZE) {
+        value |= ACE4_SYNCHRONIZE;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_encode_and_decode() {
+        let acl = Acl {
+            aces: vec![
+                Ace {
+                    ace_type: AceType::Allow,
+                    ace_flags: AceFlags::DIRECTORY_INHERIT | AceFlags::FILE_INHERIT,
+                    ace_principals: AcePrincipals("OWNER@".to_string()),
+                    ace_permissions: AcePermissions::READ_DATA | AcePermissions::EXECUTE,
+                },
+                Ace {
+                    ace_type: AceType::Deny,
+                    ace_flags: AceFlags::GROUP,
+                    ace_principals: AcePrincipals("staff".to_string()),
+                    ace_permissions: AcePermissions::WRITE_DATA,
+                },
+            ],
+        };
+
+        let encoded = encode_acl(&acl);
+        let decoded = decode_acl(&encoded).expect("decode of freshly-encoded ACL must succeed");
+
+        assert_eq!(decoded.aces, acl.aces);
+    }
+
+    #[test]
+    fn decode_rejects_unknown_ace_type() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&1u32.to_be_bytes()); // count
+        bytes.extend_from_slice(&0xAAAA_AAAAu32.to_be_bytes()); // bogus type
+        bytes.extend_from_slice(&0u32.to_be_bytes()); // flags
+        bytes.extend_from_slice(&0u32.to_be_bytes()); // mask
+        bytes.extend_from_slice(&0u32.to_be_bytes()); // principal length
+
+        assert!(decode_acl(&bytes).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_ace_count_that_overruns_the_buffer() {
+        // A corrupted or truncated xattr claiming far more ACEs than the
+        // buffer could possibly hold must not be allowed to drive a huge
+        // `Vec::with_capacity` allocation.
+        let bytes = 0xFFFF_FFFFu32.to_be_bytes();
+
+        let error = decode_acl(&bytes).expect_err("bogus ACE count must be rejected");
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decode_rejects_truncated_buffer() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+
+        assert!(decode_acl(&bytes).is_err());
+    }
+}